@@ -0,0 +1,108 @@
+use crate::pixelformat::{yuyv422_to_rgba, PixelSource};
+use epaint::ColorImage;
+use image::codecs::jpeg::JpegDecoder;
+use image::DynamicImage;
+use linux_video::types::{In, Mmap};
+use linux_video::Stream;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// A decoded frame together with the raw bytes it came from, so a
+/// snapshot can be saved without re-capturing: MJPEG frames are written
+/// through mostly unchanged, while other sources are re-encoded from the
+/// already-decoded RGBA.
+#[derive(Clone)]
+pub struct Frame {
+    pub image: ColorImage,
+    pub source: PixelSource,
+    pub raw: Arc<[u8]>,
+}
+
+/// Decodes MJPEG frames (or converts YUYV frames) to RGBA off a `Stream`
+/// on a dedicated thread and publishes
+/// the most recently decoded frame for the UI thread to pick up.
+///
+/// Decoding happens here instead of in `MyApp::update` so a slow frame
+/// never stalls rendering: the UI thread only ever reads whatever is
+/// currently in the slot, dropping older frames if it can't keep up.
+pub struct CaptureThread {
+    latest: Arc<Mutex<Option<Frame>>>,
+    handle: Option<JoinHandle<()>>,
+    stop: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl CaptureThread {
+    pub fn spawn(
+        mut stream: Stream<In, Mmap>,
+        source: PixelSource,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let latest = Arc::new(Mutex::new(None));
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let thread_latest = Arc::clone(&latest);
+        let thread_stop = Arc::clone(&stop);
+        let handle = std::thread::spawn(move || {
+            while !thread_stop.load(std::sync::atomic::Ordering::Relaxed) {
+                match Self::decode_next(&mut stream, source, width, height) {
+                    Ok(frame) => {
+                        *thread_latest.lock().unwrap() = Some(frame);
+                    }
+                    Err(err) => {
+                        println!("error capturing frame: {}", err);
+                    }
+                }
+            }
+        });
+
+        Self {
+            latest,
+            handle: Some(handle),
+            stop,
+        }
+    }
+
+    fn decode_next(
+        stream: &mut Stream<In, Mmap>,
+        source: PixelSource,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<Frame> {
+        let buf_ref = stream
+            .next()
+            .map_err(|err| anyhow::Error::msg(format!("cannot get frame: {}", err)))?;
+        let locked = buf_ref.lock();
+        let buf = locked.as_ref();
+        let raw: Arc<[u8]> = Arc::from(buf);
+
+        let rgba8 = match source {
+            PixelSource::Mjpeg => {
+                let decoder = JpegDecoder::new(buf)
+                    .map_err(|err| anyhow::Error::msg(format!("cannot get decoder: {}", err)))?;
+                DynamicImage::from_decoder(decoder)
+                    .map_err(|err| anyhow::Error::msg(format!("cannot decode frame: {}", err)))?
+                    .to_rgba8()
+                    .into_raw()
+            }
+            PixelSource::Yuyv => yuyv422_to_rgba(buf, width as usize, height as usize),
+        };
+
+        let image = ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &rgba8);
+        Ok(Frame { image, source, raw })
+    }
+
+    /// Returns the most recently decoded frame, if one is available yet.
+    pub fn latest_frame(&self) -> Option<Frame> {
+        self.latest.lock().unwrap().clone()
+    }
+}
+
+impl Drop for CaptureThread {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use linux_video::types::{Control as RawControl, ControlId, ControlType};
+use linux_video::Device;
+
+/// The value domain of a single V4L2 control, as reported by
+/// `VIDIOC_QUERYCTRL`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControlKind {
+    Integer { min: i64, max: i64, step: i64 },
+    Boolean,
+    Menu { min: i64, max: i64 },
+}
+
+/// Whether a control can currently be changed. A control can be
+/// `inactive` without being `disabled`, e.g. `exposure_abs` while
+/// auto-exposure is driving the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ControlFlags {
+    pub disabled: bool,
+    pub inactive: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Control {
+    pub id: u32,
+    pub name: String,
+    pub kind: ControlKind,
+    pub value: i64,
+    pub flags: ControlFlags,
+}
+
+/// Wraps a `Device`'s V4L2 control interface so callers can enumerate the
+/// controls a camera supports and read or drive them live, the way the
+/// `uvc` crate exposes `ae_mode`, `exposure_abs` and `focus_abs`.
+pub struct ControlPanel {
+    device: Device,
+    controls: Vec<Control>,
+}
+
+impl ControlPanel {
+    pub fn new(device: Device) -> anyhow::Result<Self> {
+        let mut panel = Self {
+            device,
+            controls: Vec::new(),
+        };
+        panel.refresh()?;
+        Ok(panel)
+    }
+
+    /// Re-queries every supported control and its current value.
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        self.controls = self
+            .device
+            .controls()
+            .into_iter()
+            .filter_map(|control| control.ok())
+            .filter_map(|raw| Self::describe(&self.device, &raw).ok())
+            .collect();
+        Ok(())
+    }
+
+    pub fn controls(&self) -> &[Control] {
+        &self.controls
+    }
+
+    pub fn set_value(&mut self, id: u32, value: i64) -> anyhow::Result<()> {
+        self.device
+            .set_control(id, value)
+            .map_err(|err| anyhow::Error::msg(format!("cannot set control {}: {}", id, err)))?;
+
+        if let Some(control) = self.controls.iter_mut().find(|c| c.id == id) {
+            control.value = value;
+        }
+        Ok(())
+    }
+
+    pub fn brightness(&self) -> Option<&Control> {
+        self.find(ControlId::Brightness)
+    }
+
+    pub fn set_brightness(&mut self, value: i64) -> anyhow::Result<()> {
+        self.set_value(ControlId::Brightness as u32, value)
+    }
+
+    pub fn contrast(&self) -> Option<&Control> {
+        self.find(ControlId::Contrast)
+    }
+
+    pub fn set_contrast(&mut self, value: i64) -> anyhow::Result<()> {
+        self.set_value(ControlId::Contrast as u32, value)
+    }
+
+    pub fn ae_mode(&self) -> Option<&Control> {
+        self.find(ControlId::ExposureAuto)
+    }
+
+    pub fn set_ae_mode(&mut self, value: i64) -> anyhow::Result<()> {
+        self.set_value(ControlId::ExposureAuto as u32, value)
+    }
+
+    pub fn exposure_abs(&self) -> Option<&Control> {
+        self.find(ControlId::ExposureAbsolute)
+    }
+
+    pub fn set_exposure_abs(&mut self, value: i64) -> anyhow::Result<()> {
+        self.set_value(ControlId::ExposureAbsolute as u32, value)
+    }
+
+    pub fn focus_abs(&self) -> Option<&Control> {
+        self.find(ControlId::FocusAbsolute)
+    }
+
+    pub fn set_focus_abs(&mut self, value: i64) -> anyhow::Result<()> {
+        self.set_value(ControlId::FocusAbsolute as u32, value)
+    }
+
+    fn find(&self, id: ControlId) -> Option<&Control> {
+        self.controls.iter().find(|c| c.id == id as u32)
+    }
+
+    fn describe(device: &Device, raw: &RawControl) -> anyhow::Result<Control> {
+        let kind = match raw.control_type() {
+            ControlType::Boolean => ControlKind::Boolean,
+            ControlType::Menu | ControlType::IntegerMenu => ControlKind::Menu {
+                min: raw.minimum(),
+                max: raw.maximum(),
+            },
+            _ => ControlKind::Integer {
+                min: raw.minimum(),
+                max: raw.maximum(),
+                step: raw.step(),
+            },
+        };
+
+        let value = device
+            .control(raw.id())
+            .map_err(|err| anyhow::Error::msg(format!("cannot read control: {}", err)))?;
+
+        Ok(Control {
+            id: raw.id(),
+            name: raw.name().to_owned(),
+            kind,
+            value,
+            flags: ControlFlags {
+                disabled: raw.is_disabled(),
+                inactive: raw.is_inactive(),
+            },
+        })
+    }
+}
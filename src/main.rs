@@ -1,23 +1,31 @@
+mod capture;
+mod controls;
+mod pixelformat;
+mod requested_format;
+mod snapshot;
+
 use anyhow::Result;
+use capture::CaptureThread;
+use controls::{ControlKind, ControlPanel};
 use eframe::egui::{self, load::DefaultBytesLoader};
 use epaint::textures::TextureOptions;
-use epaint::ColorImage;
-use image::codecs::jpeg::JpegDecoder;
-use image::DynamicImage;
-use linux_video::types::{ContentType, FrmIvalDiscrete, In, Mmap};
+use linux_video::types::{ContentType, In, Mmap};
 use linux_video::Stream;
 use linux_video::{
-    types::{BufferType, CapabilityFlag, FourCc, PixFormat},
+    types::{BufferType, CapabilityFlag, CaptureParm, PixFormat},
     Device,
 };
+use requested_format::{resolve, NegotiatedFormat, RequestedFormat};
+use snapshot::SnapshotMetadata;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const CARD: &'static str = "USB 2.0 Camera: HD USB Camera";
 const CAPS_REQ_1: CapabilityFlag = CapabilityFlag::VideoCapture;
 const CAPS_REQ_2: CapabilityFlag = CapabilityFlag::ExtPixFormat;
 const CAPS_REQ_3: CapabilityFlag = CapabilityFlag::Streaming;
 
-fn get_camera() -> Result<Device> {
+fn get_camera(requested: RequestedFormat) -> Result<(Device, String, NegotiatedFormat)> {
     let mut devs = Device::list()?;
 
     let caps_req = CAPS_REQ_1 | CAPS_REQ_2 | CAPS_REQ_3;
@@ -27,7 +35,7 @@ fn get_camera() -> Result<Device> {
             let caps = dev.capabilities()?;
 
             if caps.card() == CARD && caps.device_capabilities() == caps_req {
-                break Some(dev);
+                break Some((dev, caps.card().to_owned()));
             } else {
                 continue;
             }
@@ -36,74 +44,55 @@ fn get_camera() -> Result<Device> {
         }
     };
 
-    let device = dev.ok_or_else(|| anyhow::Error::msg("cannot find camera"))?;
-
-    let (pixels, _size, _interval) = device
-        .formats(BufferType::VideoCapture)
-        .into_iter()
-        .find_map(|format| {
-            format
-                .map(|f| {
-                    if f.pixel_format() == FourCc::Mjpeg {
-                        Some(f)
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or(None)
-        })
-        .ok_or_else(|| anyhow::Error::msg("MJPG format not supported"))
-        .and_then(|format| {
-            device
-                .sizes(format.pixel_format())
-                .into_iter()
-                .filter_map(|f| if let Ok(f) = f { Some(f) } else { None })
-                .find_map(|size| {
-                    if size.sizes().any(|s| s.width() == 320 && s.height() == 240) {
-                        Some((format, size))
-                    } else {
-                        None
-                    }
-                })
-                .ok_or_else(|| anyhow::Error::msg("320x240 resolution not supported"))
-        })
-        .and_then(|(format, size)| {
-            device
-                .intervals(format.pixel_format(), 320, 240)
-                .into_iter()
-                .filter_map(|interval| if let Ok(i) = interval { Some(i) } else { None })
-                .find_map(|interval| {
-                    interval.try_ref::<FrmIvalDiscrete>().and_then(|frac| {
-                        if frac.numerator() == 513 && frac.denominator() == 61612 {
-                            Some(interval)
-                        } else {
-                            None
-                        }
-                    })
-                })
-                .map(|interval| (format, size, interval))
-                .ok_or_else(|| anyhow::Error::msg("interval 513/61612 not supported"))
-        })?;
+    let (device, card) = dev.ok_or_else(|| anyhow::Error::msg("cannot find camera"))?;
+    let negotiated = resolve(&device, requested)?;
 
     let mut capture_format = device.format(BufferType::VideoCapture)?;
     capture_format
         .try_mut::<PixFormat>()
         .map(|pix| {
-            pix.set_pixel_format(pixels.pixel_format());
-            pix.set_width(320);
-            pix.set_height(240);
+            pix.set_pixel_format(negotiated.source.fourcc());
+            pix.set_width(negotiated.width);
+            pix.set_height(negotiated.height);
         })
         .ok_or_else(|| anyhow::Error::msg("cannot set pixel format"))?;
     device.set_format(&mut capture_format)?;
 
-    Ok(device)
+    // The resolver also chose a frame interval, not just a resolution;
+    // push it down via VIDIOC_S_PARM so the negotiated fps is actually
+    // what the device streams, not just what gets scored.
+    let mut stream_parm = device.parm(BufferType::VideoCapture)?;
+    stream_parm
+        .try_mut::<CaptureParm>()
+        .map(|parm| {
+            parm.set_timeperframe(
+                negotiated.interval_numerator,
+                negotiated.interval_denominator,
+            );
+        })
+        .ok_or_else(|| anyhow::Error::msg("cannot set frame interval"))?;
+    device.set_parm(&mut stream_parm)?;
+
+    Ok((device, card, negotiated))
 }
 
 fn main() -> Result<()> {
-    let camera = get_camera()?;
+    let (camera, card, negotiated) = get_camera(RequestedFormat::Closest {
+        width: 320,
+        height: 240,
+        fps: 30.0,
+    })?;
     let stream = camera.stream::<In, Mmap>(ContentType::Video, 4)?;
+    let capture = CaptureThread::spawn(
+        stream,
+        negotiated.source,
+        negotiated.width,
+        negotiated.height,
+    );
+    let controls = ControlPanel::new(camera)?;
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([320.0, 240.0]),
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([negotiated.width as f32, negotiated.height as f32]),
         ..Default::default()
     };
     eframe::run_native(
@@ -114,7 +103,7 @@ fn main() -> Result<()> {
             egui_extras::install_image_loaders(&cc.egui_ctx);
             cc.egui_ctx
                 .add_bytes_loader(Arc::new(DefaultBytesLoader::default()));
-            Box::<MyApp>::new(MyApp::new(stream))
+            Box::<MyApp>::new(MyApp::new(capture, controls, card))
         }),
     )
     .map_err(|err| anyhow::Error::msg(err.to_string()))
@@ -123,17 +112,47 @@ fn main() -> Result<()> {
 struct MyApp {
     name: String,
     age: u32,
-    stream: Stream<In, Mmap>,
+    capture: CaptureThread,
+    controls: ControlPanel,
+    camera_model: String,
+    snapshot_status: Option<String>,
 }
 
 impl MyApp {
-    fn new(stream: Stream<In, Mmap>) -> Self {
+    fn new(capture: CaptureThread, controls: ControlPanel, camera_model: String) -> Self {
         Self {
             name: "Arthur".to_owned(),
             age: 42,
-            stream,
+            capture,
+            controls,
+            camera_model,
+            snapshot_status: None,
         }
     }
+
+    fn take_snapshot(&mut self) {
+        let Some(frame) = self.capture.latest_frame() else {
+            self.snapshot_status = Some("no frame captured yet".to_owned());
+            return;
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let path = format!("snapshot-{}.jpg", timestamp);
+        let metadata = SnapshotMetadata {
+            timestamp,
+            width: frame.image.size[0] as u32,
+            height: frame.image.size[1] as u32,
+            camera_model: &self.camera_model,
+        };
+
+        self.snapshot_status = Some(match snapshot::save_snapshot(&path, &frame, &metadata) {
+            Ok(()) => format!("saved {}", path),
+            Err(err) => format!("error saving snapshot: {}", err),
+        });
+    }
 }
 
 impl eframe::App for MyApp {
@@ -151,33 +170,56 @@ impl eframe::App for MyApp {
             }
             ui.label(format!("Hello '{}', age {}", self.name, self.age));
 
-            self.stream
-                .next()
-                .map_err(|err| anyhow::Error::msg(format!("cannot get frame: {}", err)))
-                .and_then(|buf_ref| {
-                    let locked = buf_ref.lock();
-                    let buf = locked.as_ref();
-                    JpegDecoder::new(buf)
-                        .map_err(|err| anyhow::Error::msg(format!("cannot get decoder: {}", err)))
-                        .and_then(|decoder| {
-                            DynamicImage::from_decoder(decoder)
-                                .map_err(|err| {
-                                    anyhow::Error::msg(format!("cannot get decoder: {}", err))
-                                })
-                                .map(|img| img.to_rgba8())
-                        })
-                        .and_then(|rgba8| {
-                            let image = ColorImage::from_rgba_unmultiplied([320, 240], &rgba8);
-                            let texture = ctx.load_texture("frame", image, TextureOptions::LINEAR);
-                            ui.image((texture.id(), texture.size_vec2()));
-                            Ok(())
-                        })
-                })
-                .map_err(|err| {
-                    println!("error displaying frame: {}", &err);
-                    err
-                })
-                .ok();
+            if let Some(frame) = self.capture.latest_frame() {
+                let texture = ctx.load_texture("frame", frame.image, TextureOptions::LINEAR);
+                ui.image((texture.id(), texture.size_vec2()));
+            }
+
+            if ui.button("Take snapshot").clicked() {
+                self.take_snapshot();
+            }
+            if let Some(status) = &self.snapshot_status {
+                ui.label(status);
+            }
+
+            ui.separator();
+            ui.heading("Camera controls");
+            let mut edits = Vec::new();
+            for control in self.controls.controls() {
+                ui.add_enabled_ui(!control.flags.disabled && !control.flags.inactive, |ui| {
+                    let mut value = control.value;
+                    let changed = match control.kind {
+                        ControlKind::Boolean => {
+                            let mut checked = value != 0;
+                            let response = ui.checkbox(&mut checked, &control.name);
+                            value = checked as i64;
+                            response.changed()
+                        }
+                        ControlKind::Integer { min, max, .. } => ui
+                            .add(egui::Slider::new(&mut value, min..=max).text(&control.name))
+                            .changed(),
+                        ControlKind::Menu { min, max } => ui
+                            .add(egui::Slider::new(&mut value, min..=max).text(&control.name))
+                            .changed(),
+                    };
+                    if changed {
+                        edits.push((control.id, value));
+                    }
+                });
+            }
+            if !edits.is_empty() {
+                for (id, value) in edits {
+                    if let Err(err) = self.controls.set_value(id, value) {
+                        println!("error setting control: {}", err);
+                    }
+                }
+                // Changing one control (e.g. disabling auto-exposure) can
+                // flip the disabled/inactive flags of others, so re-query
+                // the whole set instead of trusting the stale snapshot.
+                if let Err(err) = self.controls.refresh() {
+                    println!("error refreshing controls: {}", err);
+                }
+            }
         });
 
         // tell egui to keep rendering
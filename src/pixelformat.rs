@@ -0,0 +1,93 @@
+use linux_video::types::FourCc;
+
+/// Which pixel format the device is streaming, and how to turn its raw
+/// buffers into RGBA for display.
+///
+/// `resolve` accepts both `Mjpeg` and `Yuyv` candidates so the crate also
+/// works with UVC webcams that only expose YUYV at their higher frame
+/// rates; which one is streamed follows from the `RequestedFormat`
+/// scoring, with no fixed preference between the two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelSource {
+    Mjpeg,
+    Yuyv,
+}
+
+impl PixelSource {
+    pub fn from_fourcc(fourcc: FourCc) -> Option<Self> {
+        match fourcc {
+            FourCc::Mjpeg => Some(Self::Mjpeg),
+            FourCc::Yuyv => Some(Self::Yuyv),
+            _ => None,
+        }
+    }
+
+    pub fn fourcc(self) -> FourCc {
+        match self {
+            Self::Mjpeg => FourCc::Mjpeg,
+            Self::Yuyv => FourCc::Yuyv,
+        }
+    }
+}
+
+/// Converts a packed YUYV422 buffer (`Y0 U Y1 V` quads, two pixels per
+/// quad sharing one chroma pair) to interleaved RGBA using the BT.601
+/// coefficients.
+pub fn yuyv422_to_rgba(buf: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut rgba = vec![0u8; width * height * 4];
+
+    for (quad, out) in buf.chunks_exact(4).zip(rgba.chunks_exact_mut(8)) {
+        let [y0, u, y1, v] = [quad[0], quad[1], quad[2], quad[3]];
+        let (r0, g0, b0) = yuv_to_rgb(y0, u, v);
+        let (r1, g1, b1) = yuv_to_rgb(y1, u, v);
+        out[0..4].copy_from_slice(&[r0, g0, b0, 255]);
+        out[4..8].copy_from_slice(&[r1, g1, b1, 255]);
+    }
+
+    rgba
+}
+
+fn yuv_to_rgb(y: u8, u: u8, v: u8) -> (u8, u8, u8) {
+    let y = y as f32;
+    let u = u as f32 - 128.0;
+    let v = v as f32 - 128.0;
+
+    let r = y + 1.402 * v;
+    let g = y - 0.344 * u - 0.714 * v;
+    let b = y + 1.772 * u;
+
+    (clamp_channel(r), clamp_channel(g), clamp_channel(b))
+}
+
+fn clamp_channel(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neutral_chroma_passes_luma_through() {
+        // U = V = 128 carries no color, so R = G = B = Y for both pixels
+        // in the quad.
+        let buf = [16u8, 128, 235, 128];
+        let rgba = yuyv422_to_rgba(&buf, 2, 1);
+        assert_eq!(rgba, vec![16, 16, 16, 255, 235, 235, 235, 255]);
+    }
+
+    #[test]
+    fn saturated_chroma_clamps_instead_of_wrapping() {
+        // Y = 0 with max chroma (U = V = 255, i.e. +127) drives G well
+        // below 0 under the raw BT.601 formulas, while R (1.402 * 127) and
+        // B (1.772 * 127) stay in range; G must clamp to 0 rather than
+        // wrap, and R/B must round to their exact bytes.
+        let buf = [0u8, 255, 0, 255];
+        let rgba = yuyv422_to_rgba(&buf, 2, 1);
+        assert_eq!(
+            rgba,
+            vec![178, 0, 225, 255, 178, 0, 225, 255],
+            "R should be 178, G should clamp to 0, B should be 225"
+        );
+    }
+}
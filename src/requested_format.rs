@@ -0,0 +1,108 @@
+use crate::pixelformat::PixelSource;
+use linux_video::types::{BufferType, FrmIvalDiscrete};
+use linux_video::Device;
+
+/// Expresses what a caller wants out of a device's format/resolution/frame
+/// rate negotiation, instead of the caller hardcoding a specific pixel
+/// format, size and interval.
+#[derive(Debug, Clone, Copy)]
+pub enum RequestedFormat {
+    /// Largest width * height the device offers, at any frame rate.
+    HighestResolution,
+    /// Highest frame rate the device offers, at any resolution.
+    HighestFrameRate,
+    /// The candidate whose width, height and frame rate are jointly
+    /// closest to the requested ones.
+    Closest { width: u32, height: u32, fps: f32 },
+    /// The first (format, size, interval) combination the device reports.
+    First,
+}
+
+/// A (pixel format, resolution, interval) combination the device actually
+/// supports, together with the chosen frame interval.
+#[derive(Debug, Clone, Copy)]
+pub struct NegotiatedFormat {
+    pub source: PixelSource,
+    pub width: u32,
+    pub height: u32,
+    pub interval_numerator: u32,
+    pub interval_denominator: u32,
+}
+
+impl NegotiatedFormat {
+    fn fps(&self) -> f32 {
+        self.interval_denominator as f32 / self.interval_numerator as f32
+    }
+}
+
+/// Enumerates the format/size/interval combinations the device reports
+/// and picks the one that best matches `requested`. Only MJPEG and YUYV
+/// formats with discrete intervals are considered; if there are none, an
+/// error is returned.
+pub fn resolve(device: &Device, requested: RequestedFormat) -> anyhow::Result<NegotiatedFormat> {
+    let mut candidates = Vec::new();
+
+    for format in device.formats(BufferType::VideoCapture) {
+        let Ok(format) = format else { continue };
+        let Some(source) = PixelSource::from_fourcc(format.pixel_format()) else {
+            continue;
+        };
+
+        for size in device.sizes(format.pixel_format()) {
+            let Ok(size) = size else { continue };
+            for frame_size in size.sizes() {
+                let width = frame_size.width();
+                let height = frame_size.height();
+
+                for interval in device.intervals(format.pixel_format(), width, height) {
+                    let Ok(interval) = interval else { continue };
+                    let Some(discrete) = interval.try_ref::<FrmIvalDiscrete>() else {
+                        continue;
+                    };
+
+                    candidates.push(NegotiatedFormat {
+                        source,
+                        width,
+                        height,
+                        interval_numerator: discrete.numerator(),
+                        interval_denominator: discrete.denominator(),
+                    });
+                }
+            }
+        }
+    }
+
+    let best = match requested {
+        RequestedFormat::First => candidates.into_iter().next(),
+        RequestedFormat::HighestResolution => candidates
+            .into_iter()
+            .max_by_key(|c| c.width as u64 * c.height as u64),
+        RequestedFormat::HighestFrameRate => candidates
+            .into_iter()
+            .max_by(|a, b| a.fps().total_cmp(&b.fps())),
+        RequestedFormat::Closest { width, height, fps } => candidates.into_iter().min_by(|a, b| {
+            closeness(a, width, height, fps).total_cmp(&closeness(b, width, height, fps))
+        }),
+    };
+
+    best.ok_or_else(|| anyhow::Error::msg("no matching format/resolution/interval found"))
+}
+
+/// Relative squared error between `candidate` and the requested
+/// width/height/fps, so resolution (O(10^2)-O(10^4) px) and frame rate
+/// (O(1)-O(10) fps) contribute comparable weight instead of the pixel
+/// terms drowning out fps.
+fn closeness(candidate: &NegotiatedFormat, width: u32, height: u32, fps: f32) -> f32 {
+    let dw = relative_error(candidate.width as f32, width as f32);
+    let dh = relative_error(candidate.height as f32, height as f32);
+    let df = relative_error(candidate.fps(), fps);
+    dw * dw + dh * dh + df * df
+}
+
+fn relative_error(candidate: f32, requested: f32) -> f32 {
+    if requested == 0.0 {
+        candidate
+    } else {
+        (candidate - requested) / requested
+    }
+}
@@ -0,0 +1,237 @@
+use crate::capture::Frame;
+use crate::pixelformat::PixelSource;
+use image::codecs::jpeg::JpegEncoder;
+use std::path::Path;
+
+/// The EXIF tags a snapshot is stamped with. `timestamp` is Unix seconds
+/// so the caller controls the clock source instead of this module
+/// reaching for one itself.
+pub struct SnapshotMetadata<'a> {
+    pub timestamp: i64,
+    pub width: u32,
+    pub height: u32,
+    pub camera_model: &'a str,
+}
+
+/// Grabs `frame` and writes it to `path` as a JPEG with an APP1/EXIF
+/// segment. MJPEG frames are passed through mostly unchanged; other
+/// sources are re-encoded from the already-decoded RGBA.
+pub fn save_snapshot(
+    path: impl AsRef<Path>,
+    frame: &Frame,
+    metadata: &SnapshotMetadata,
+) -> anyhow::Result<()> {
+    let jpeg = match frame.source {
+        PixelSource::Mjpeg => frame.raw.to_vec(),
+        PixelSource::Yuyv => encode_rgba_as_jpeg(&frame.image, metadata.width, metadata.height)?,
+    };
+
+    let with_exif = inject_exif(&jpeg, metadata)?;
+    std::fs::write(path, with_exif)
+        .map_err(|err| anyhow::Error::msg(format!("cannot write snapshot: {}", err)))
+}
+
+fn encode_rgba_as_jpeg(
+    image: &epaint::ColorImage,
+    width: u32,
+    height: u32,
+) -> anyhow::Result<Vec<u8>> {
+    let rgb: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|p| [p.r(), p.g(), p.b()])
+        .collect();
+
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, 90)
+        .encode(&rgb, width, height, image::ExtendedColorType::Rgb8)
+        .map_err(|err| anyhow::Error::msg(format!("cannot encode snapshot: {}", err)))?;
+    Ok(out)
+}
+
+/// Splices a minimal APP1/EXIF segment (TIFF header, `IFD0` with
+/// `ImageWidth`/`ImageLength`/`Make`/`Model`/`DateTime`/`Orientation`, no
+/// thumbnail) right after the JPEG's SOI marker.
+fn inject_exif(jpeg: &[u8], metadata: &SnapshotMetadata) -> anyhow::Result<Vec<u8>> {
+    if jpeg.len() < 2 || jpeg[0..2] != [0xFF, 0xD8] {
+        return Err(anyhow::Error::msg("not a JPEG stream (missing SOI marker)"));
+    }
+
+    let segment = build_exif_segment(metadata);
+
+    let mut out = Vec::with_capacity(jpeg.len() + segment.len());
+    out.extend_from_slice(&jpeg[0..2]);
+    out.extend_from_slice(&segment);
+    out.extend_from_slice(&jpeg[2..]);
+    Ok(out)
+}
+
+fn build_exif_segment(metadata: &SnapshotMetadata) -> Vec<u8> {
+    const TAG_IMAGE_WIDTH: u16 = 0x0100;
+    const TAG_IMAGE_LENGTH: u16 = 0x0101;
+    const TAG_MAKE: u16 = 0x010F;
+    const TAG_MODEL: u16 = 0x0110;
+    const TAG_ORIENTATION: u16 = 0x0112;
+    const TAG_DATE_TIME: u16 = 0x0132;
+    const TYPE_SHORT: u16 = 3;
+    const TYPE_LONG: u16 = 4;
+
+    let date_time = format_exif_timestamp(metadata.timestamp);
+    let make = c_string("camera-capture-v4l2-rs");
+    let model = c_string(metadata.camera_model);
+    let date_time = c_string(&date_time);
+
+    // TIFF header (8 bytes) + IFD0 entry count (2) + 6 entries (12 bytes
+    // each) + next-IFD offset (4) + the out-of-line ASCII values.
+    let ifd0_offset = 8u32;
+    let entry_count = 6u16;
+    let data_area_offset = ifd0_offset + 2 + (entry_count as u32) * 12 + 4;
+
+    let mut tiff = Vec::new();
+    tiff.extend_from_slice(b"II"); // little-endian
+    tiff.extend_from_slice(&42u16.to_le_bytes());
+    tiff.extend_from_slice(&ifd0_offset.to_le_bytes());
+
+    let mut data_area = Vec::new();
+    let mut entries = Vec::new();
+    let mut next_data_offset = data_area_offset;
+
+    // ImageWidth/ImageLength (LONG, count 1) fit inline in the
+    // value/offset field, same as Orientation below. Tags are emitted in
+    // ascending order, as the TIFF spec requires.
+    push_inline_entry(
+        &mut entries,
+        TAG_IMAGE_WIDTH,
+        TYPE_LONG,
+        &metadata.width.to_le_bytes(),
+    );
+    push_inline_entry(
+        &mut entries,
+        TAG_IMAGE_LENGTH,
+        TYPE_LONG,
+        &metadata.height.to_le_bytes(),
+    );
+    push_ascii_entry(
+        &mut entries,
+        &mut data_area,
+        &mut next_data_offset,
+        TAG_MAKE,
+        &make,
+    );
+    push_ascii_entry(
+        &mut entries,
+        &mut data_area,
+        &mut next_data_offset,
+        TAG_MODEL,
+        &model,
+    );
+    // Orientation 1 = top-left, no rotation; padded to fill the 4-byte
+    // value/offset field.
+    push_inline_entry(&mut entries, TAG_ORIENTATION, TYPE_SHORT, &[1, 0, 0, 0]);
+    push_ascii_entry(
+        &mut entries,
+        &mut data_area,
+        &mut next_data_offset,
+        TAG_DATE_TIME,
+        &date_time,
+    );
+
+    tiff.extend_from_slice(&entry_count.to_le_bytes());
+    tiff.extend_from_slice(&entries);
+    tiff.extend_from_slice(&0u32.to_le_bytes()); // no next IFD
+    tiff.extend_from_slice(&data_area);
+
+    let exif_header = b"Exif\0\0";
+    let payload_len = exif_header.len() + tiff.len();
+    let segment_len = payload_len + 2; // + the length field itself
+
+    let mut segment = Vec::with_capacity(segment_len + 2);
+    segment.extend_from_slice(&[0xFF, 0xE1]);
+    segment.extend_from_slice(&(segment_len as u16).to_be_bytes());
+    segment.extend_from_slice(exif_header);
+    segment.extend_from_slice(&tiff);
+    segment
+}
+
+/// Appends an IFD entry whose value fits inline in the 4-byte
+/// value/offset field (a single LONG/SHORT, already little-endian and
+/// padded to 4 bytes).
+fn push_inline_entry(entries: &mut Vec<u8>, tag: u16, ty: u16, value: &[u8; 4]) {
+    entries.extend_from_slice(&tag.to_le_bytes());
+    entries.extend_from_slice(&ty.to_le_bytes());
+    entries.extend_from_slice(&1u32.to_le_bytes());
+    entries.extend_from_slice(value);
+}
+
+/// Appends an IFD entry for an out-of-line ASCII value: the entry's
+/// value/offset field points into `data_area`, and `next_data_offset` is
+/// advanced past the bytes just written there.
+fn push_ascii_entry(
+    entries: &mut Vec<u8>,
+    data_area: &mut Vec<u8>,
+    next_data_offset: &mut u32,
+    tag: u16,
+    value: &[u8],
+) {
+    const TYPE_ASCII: u16 = 2;
+
+    entries.extend_from_slice(&tag.to_le_bytes());
+    entries.extend_from_slice(&TYPE_ASCII.to_le_bytes());
+    entries.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    entries.extend_from_slice(&next_data_offset.to_le_bytes());
+    data_area.extend_from_slice(value);
+    *next_data_offset += value.len() as u32;
+}
+
+fn c_string(value: &str) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+fn format_exif_timestamp(unix_seconds: i64) -> String {
+    // EXIF wants "YYYY:MM:DD HH:MM:SS"; a tiny civil-calendar conversion
+    // keeps this module free of an extra date/time dependency.
+    let days = unix_seconds.div_euclid(86_400);
+    let secs_of_day = unix_seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    let hour = secs_of_day / 3600;
+    let minute = (secs_of_day % 3600) / 60;
+    let second = secs_of_day % 60;
+    format!(
+        "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// Howard Hinnant's `civil_from_days` algorithm, converting a day count
+/// since the Unix epoch into a proleptic-Gregorian (year, month, day).
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = (z - era * 146_097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = if m <= 2 { y + 1 } else { y };
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_the_unix_epoch() {
+        assert_eq!(format_exif_timestamp(0), "1970:01:01 00:00:00");
+    }
+
+    #[test]
+    fn formats_a_known_timestamp() {
+        // 2024-03-05 09:08:07 UTC.
+        assert_eq!(format_exif_timestamp(1_709_629_687), "2024:03:05 09:08:07");
+    }
+}